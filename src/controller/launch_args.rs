@@ -0,0 +1,90 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Extra CLI arguments a service was installed with, persisted next to the
+/// service executable (`<exe>.config`) so they are picked up again by
+/// `dispatch` on every future start, instead of only the arguments the SCM
+/// or init system happens to pass in.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LaunchArgsFile {
+    args: Vec<String>,
+}
+
+fn sidecar_path(exe_path: &Path) -> PathBuf {
+    let mut path = exe_path.as_os_str().to_owned();
+    path.push(".config");
+    PathBuf::from(path)
+}
+
+/// Serializes `args` to the sidecar file next to `exe_path`.
+pub fn save(exe_path: &Path, args: &[String]) -> Result<(), Error> {
+    let path = sidecar_path(exe_path);
+    let content = serde_json::to_string_pretty(&LaunchArgsFile {
+        args: args.to_vec(),
+    })
+    .map_err(|e| Error::new(&format!("Failed to serialize launch args: {}", e)))?;
+
+    File::create(&path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Loads the persisted arguments for `exe_path`, if any were saved.
+pub fn load(exe_path: &Path) -> Vec<String> {
+    let path = sidecar_path(exe_path);
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<LaunchArgsFile>(&content).ok())
+        .map(|file| file.args)
+        .unwrap_or_default()
+}
+
+/// Removes the sidecar file, if one was written.
+pub fn delete(exe_path: &Path) {
+    let path = sidecar_path(exe_path);
+    let _ = fs::remove_file(&path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_exe_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("ceviche-rs-launch-args-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn save_then_load_round_trips_args() {
+        let exe_path = temp_exe_path("round-trip");
+        let args = vec!["--foo".to_string(), "bar".to_string()];
+
+        assert!(save(&exe_path, &args).is_ok());
+        assert_eq!(load(&exe_path), args);
+
+        delete(&exe_path);
+    }
+
+    #[test]
+    fn load_without_a_saved_file_returns_empty() {
+        let exe_path = temp_exe_path("missing");
+
+        assert!(load(&exe_path).is_empty());
+    }
+
+    #[test]
+    fn delete_removes_the_sidecar_file() {
+        let exe_path = temp_exe_path("delete");
+        save(&exe_path, &["--baz".to_string()]).unwrap();
+
+        delete(&exe_path);
+
+        assert!(load(&exe_path).is_empty());
+        assert!(!sidecar_path(&exe_path).exists());
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs::{self, read, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{debug, info};
+
+use crate::Error;
+
+use super::{ActiveState, InactiveState, ServiceState, ServiceStatus, SystemServiceManager};
+
+fn service(service_name: &str, args: &[&str]) -> Result<String, Error> {
+    let mut process = Command::new("service");
+    process.arg(service_name).args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute service: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(&format!(
+            "service {} {:?} failed: {}",
+            service_name,
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Runs `service <service_name> <args>` and returns its stdout regardless of
+/// exit code. BSD's `service status` exits non-zero when the service is not
+/// running, so only a genuine failure to spawn the process is an `Err` here.
+fn service_status(service_name: &str, args: &[&str]) -> Result<String, Error> {
+    let mut process = Command::new("service");
+    process.arg(service_name).args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute service: {}", e)))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn sysrc(args: &[&str]) -> Result<(), Error> {
+    let mut process = Command::new("sysrc");
+    process.args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute sysrc: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(&format!(
+            "sysrc {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Drives a BSD-style `rc.d` service through the `service` command, with
+/// `${name}_enable` toggled in `rc.conf` via `sysrc`.
+#[derive(Default)]
+pub struct BsdRcManager;
+
+impl BsdRcManager {
+    fn get_script_path(&self, service_name: &str) -> PathBuf {
+        Path::new("/usr/local/etc/rc.d/").join(service_name)
+    }
+
+    fn get_pid_path(&self, service_name: &str) -> PathBuf {
+        Path::new("/var/run/").join(format!("{}.pid", service_name))
+    }
+
+    fn get_script_content(&self, service_name: &str, exec_start: &str) -> String {
+        format!(
+            r#"#!/bin/sh
+# PROVIDE: {name}
+# REQUIRE: LOGIN
+
+. /etc/rc.subr
+
+name="{name}"
+rcvar="{name}_enable"
+command="{exec_start}"
+pidfile="/var/run/{name}.pid"
+
+load_rc_config "$name"
+run_rc_command "$1"
+"#,
+            name = service_name,
+            exec_start = exec_start,
+        )
+    }
+}
+
+impl SystemServiceManager for BsdRcManager {
+    fn install(
+        &self,
+        service_name: &str,
+        _display_name: &str,
+        exec_start: &str,
+        config: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = self.get_script_path(service_name);
+        let content = self.get_script_content(service_name, exec_start);
+        info!("Writing rc.d script {}", path.display());
+        File::create(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+
+        let mut perms = fs::metadata(&path)
+            .map_err(|e| Error::new(&format!("Failed to stat {}: {}", path.display(), e)))?
+            .permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut perms, 0o755);
+        fs::set_permissions(&path, perms)
+            .map_err(|e| Error::new(&format!("Failed to chmod {}: {}", path.display(), e)))?;
+
+        if let Some(config) = config {
+            let path = Path::new("/usr/local/etc/").join(format!("{}.conf", service_name));
+            info!("Writing config file {}", path.display());
+            File::create(&path)
+                .and_then(|mut file| file.write_all(config.as_bytes()))
+                .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+
+        sysrc(&[&format!("{}_enable=YES", service_name)])
+    }
+
+    fn uninstall(&self, service_name: &str) -> Result<(), Error> {
+        sysrc(&["-x", &format!("{}_enable", service_name)])
+            .map_err(|e| debug!("{}", e))
+            .ok();
+
+        let path = self.get_script_path(service_name);
+        fs::remove_file(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        let path = Path::new("/usr/local/etc/").join(format!("{}.conf", service_name));
+        fs::remove_file(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        Ok(())
+    }
+
+    fn start(&self, service_name: &str) -> Result<(), Error> {
+        service(service_name, &["start"]).map(|_| ())
+    }
+
+    fn stop(&self, service_name: &str) -> Result<(), Error> {
+        service(service_name, &["stop"]).map(|_| ())
+    }
+
+    fn status(&self, service_name: &str) -> Result<ServiceStatus, Error> {
+        let result = service_status(service_name, &["status"])?;
+
+        let pid = read(self.get_pid_path(service_name))
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .unwrap_or(0);
+
+        let cmdline = if pid != 0 {
+            read(&format!("/proc/{}/cmdline", pid))
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let state = if result.contains("is running") {
+            ServiceState::Active(ActiveState::Running)
+        } else if result.contains("is not running") {
+            ServiceState::Inactive(InactiveState::Dead)
+        } else {
+            return Err(Error::new(&format!("Invalid service status: {}", result)));
+        };
+
+        Ok(ServiceStatus {
+            state,
+            cmdline,
+            pid,
+            is_failed: false,
+            details: result,
+        })
+    }
+}
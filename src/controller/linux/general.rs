@@ -0,0 +1,167 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use log::{debug, info};
+
+use crate::Error;
+
+use super::{ActiveState, InactiveState, ServiceState, ServiceStatus, SystemServiceManager};
+
+/// Describes how to drive an init system ceviche has no dedicated backend
+/// for: the commands to run for each lifecycle operation and where to drop
+/// the rendered unit file. Every command is a list of argv entries in which
+/// `{name}` is replaced with the service name before execution.
+pub struct GeneralManagerConfig {
+    pub unit_template_path: PathBuf,
+    pub install_path: PathBuf,
+    pub enable_command: Vec<String>,
+    pub disable_command: Vec<String>,
+    pub start_command: Vec<String>,
+    pub stop_command: Vec<String>,
+    pub status_command: Vec<String>,
+    /// Substring `status_command`'s output must contain for the service to
+    /// be considered active.
+    pub status_running_needle: String,
+}
+
+/// Drives an arbitrary init system described by a [`GeneralManagerConfig`],
+/// for systems ceviche has no dedicated backend for.
+pub struct GeneralManager {
+    config: GeneralManagerConfig,
+}
+
+impl GeneralManager {
+    pub fn new(config: GeneralManagerConfig) -> GeneralManager {
+        GeneralManager { config }
+    }
+
+    fn render(template: &[String], service_name: &str) -> Vec<String> {
+        template
+            .iter()
+            .map(|arg| arg.replace("{name}", service_name))
+            .collect()
+    }
+
+    fn run(args: &[String]) -> Result<String, Error> {
+        let (command, rest) = args
+            .split_first()
+            .ok_or_else(|| Error::new("Empty command"))?;
+
+        let output = Command::new(command)
+            .args(rest)
+            .output()
+            .map_err(|e| Error::new(&format!("Failed to execute {}: {}", command, e)))?;
+
+        if !output.status.success() {
+            return Err(Error::new(&format!(
+                "Command \"{}\" failed: {}",
+                command,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        if !output.stdout.is_empty() {
+            info!("{}", String::from_utf8_lossy(&output.stdout));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Like `run`, but returns stdout regardless of exit code: many init
+    /// systems' status commands (OpenRC, BSD rc.d, ...) exit non-zero for
+    /// "not running", so `status_command` can't be judged a failure just
+    /// because the process didn't exit 0. Only a genuine failure to spawn
+    /// the process is an `Err` here.
+    fn run_status(args: &[String]) -> Result<String, Error> {
+        let (command, rest) = args
+            .split_first()
+            .ok_or_else(|| Error::new("Empty command"))?;
+
+        let output = Command::new(command)
+            .args(rest)
+            .output()
+            .map_err(|e| Error::new(&format!("Failed to execute {}: {}", command, e)))?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl SystemServiceManager for GeneralManager {
+    fn install(
+        &self,
+        service_name: &str,
+        _display_name: &str,
+        exec_start: &str,
+        _config: Option<&str>,
+    ) -> Result<(), Error> {
+        let template = fs::read_to_string(&self.config.unit_template_path).map_err(|e| {
+            Error::new(&format!(
+                "Failed to read {}: {}",
+                self.config.unit_template_path.display(),
+                e
+            ))
+        })?;
+        let content = template
+            .replace("{name}", service_name)
+            .replace("{exec_start}", exec_start);
+
+        info!("Writing service file {}", self.config.install_path.display());
+        File::create(&self.config.install_path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| {
+                Error::new(&format!(
+                    "Failed to write {}: {}",
+                    self.config.install_path.display(),
+                    e
+                ))
+            })?;
+
+        Self::run(&Self::render(&self.config.enable_command, service_name)).map(|_| ())
+    }
+
+    fn uninstall(&self, service_name: &str) -> Result<(), Error> {
+        Self::run(&Self::render(&self.config.disable_command, service_name))
+            .map_err(|e| debug!("{}", e))
+            .ok();
+
+        fs::remove_file(&self.config.install_path)
+            .map_err(|e| {
+                debug!(
+                    "Failed to delete {}: {}",
+                    self.config.install_path.display(),
+                    e
+                )
+            })
+            .ok();
+
+        Ok(())
+    }
+
+    fn start(&self, service_name: &str) -> Result<(), Error> {
+        Self::run(&Self::render(&self.config.start_command, service_name)).map(|_| ())
+    }
+
+    fn stop(&self, service_name: &str) -> Result<(), Error> {
+        Self::run(&Self::render(&self.config.stop_command, service_name)).map(|_| ())
+    }
+
+    fn status(&self, service_name: &str) -> Result<ServiceStatus, Error> {
+        let result = Self::run_status(&Self::render(&self.config.status_command, service_name))?;
+
+        let state = if result.contains(&self.config.status_running_needle) {
+            ServiceState::Active(ActiveState::Running)
+        } else {
+            ServiceState::Inactive(InactiveState::Dead)
+        };
+
+        Ok(ServiceStatus {
+            state,
+            cmdline: String::new(),
+            pid: 0,
+            is_failed: false,
+            details: result,
+        })
+    }
+}
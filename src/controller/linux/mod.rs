@@ -0,0 +1,439 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+
+use ctrlc;
+use log::debug;
+use systemd_rs::login::monitor::{Category, Monitor};
+use systemd_rs::login::session as login_session;
+
+use crate::controller::{ControllerInterface, ServiceMainFn, ServiceResult};
+use crate::session;
+use crate::Error;
+use crate::ServiceEvent;
+
+use super::BasicServiceStatus;
+
+mod bsdrc;
+mod general;
+mod openrc;
+mod systemd;
+
+pub use self::bsdrc::BsdRcManager;
+pub use self::general::{GeneralManager, GeneralManagerConfig};
+pub use self::openrc::OpenRcManager;
+pub use self::systemd::SystemdManager;
+
+type LinuxServiceMainWrapperFn = extern "system" fn(args: Vec<String>);
+pub type Session = session::Session_<String>;
+
+#[derive(Debug)]
+pub enum ActiveState {
+    Running,
+    Exited,
+    Waiting,
+    Dead,
+}
+
+#[derive(Debug)]
+pub enum InactiveState {
+    Dead,
+    Exited,
+    Waiting,
+    Resetting,
+}
+
+#[derive(Debug)]
+pub enum ServiceState {
+    Active(ActiveState),
+    Inactive(InactiveState),
+}
+
+#[derive(Debug)]
+pub struct ServiceStatus {
+    pub state: ServiceState,
+    pub cmdline: String,
+    pub pid: u32,
+    pub is_failed: bool,
+    pub details: String,
+}
+
+impl ServiceStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(&self.state, ServiceState::Active(_))
+    }
+    pub fn is_inactive(&self) -> bool {
+        matches!(&self.state, ServiceState::Inactive(_))
+    }
+}
+
+impl BasicServiceStatus for ServiceStatus {
+    fn is_running(&self) -> bool {
+        matches!(&self.state, ServiceState::Active(state) if matches!(state, ActiveState::Running))
+    }
+
+    fn is_failed(&self) -> bool {
+        self.is_failed
+    }
+
+    fn get_cmdline(&self) -> &str {
+        &self.cmdline
+    }
+}
+
+/// Backend able to drive a single init system (systemd, OpenRC, BSD rc.d, or
+/// a user-supplied "general" backend) through the handful of operations a
+/// `LinuxController` needs. Each implementation owns its own service
+/// description file format and knows how to turn its own status output into
+/// the shared [`ServiceStatus`].
+pub trait SystemServiceManager {
+    /// Writes the service description file (unit, init script, ...) and
+    /// registers it with the init system so it starts on boot. `config`, if
+    /// set, is dropped alongside the description file (e.g. a systemd
+    /// drop-in) for backends that support it.
+    fn install(
+        &self,
+        service_name: &str,
+        display_name: &str,
+        exec_start: &str,
+        config: Option<&str>,
+    ) -> Result<(), Error>;
+
+    /// Unregisters the service and removes its description file.
+    fn uninstall(&self, service_name: &str) -> Result<(), Error>;
+
+    fn start(&self, service_name: &str) -> Result<(), Error>;
+
+    fn stop(&self, service_name: &str) -> Result<(), Error>;
+
+    fn status(&self, service_name: &str) -> Result<ServiceStatus, Error>;
+}
+
+/// Probes the running system for a live init system and returns the
+/// matching [`SystemServiceManager`], preferring systemd when
+/// `/run/systemd/system` is present, then OpenRC when `rc-service` is on
+/// `PATH`. This module is only compiled for `target_os = "linux"`, so the
+/// BSD branch below never matches and [`BsdRcManager`] is never picked
+/// automatically; it is used only when passed to
+/// [`LinuxController::with_manager`]. Anything else
+/// (e.g. plain SysVinit) has no auto-detected backend and returns a manager
+/// that fails every call with instructions to build the controller with
+/// [`LinuxController::with_manager`] and a [`GeneralManager`] instead —
+/// guessing BSD's `sysrc`/`service` commands on a SysVinit box would just
+/// fail with a confusing "command not found".
+pub fn detect_service_manager() -> Box<dyn SystemServiceManager> {
+    if Path::new("/run/systemd/system").is_dir() {
+        Box::new(SystemdManager::default())
+    } else if which("rc-service") {
+        Box::new(OpenRcManager::default())
+    } else if cfg!(any(
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    )) {
+        Box::new(BsdRcManager::default())
+    } else {
+        Box::new(UnknownInitManager)
+    }
+}
+
+/// Returned by [`detect_service_manager`] when no supported init system
+/// could be identified. Every operation fails, pointing the caller at
+/// [`LinuxController::with_manager`] and [`GeneralManager`] instead of
+/// silently driving the wrong init system's commands.
+struct UnknownInitManager;
+
+impl UnknownInitManager {
+    fn error() -> Error {
+        Error::new(
+            "Could not detect a supported init system (systemd, OpenRC); build the controller \
+             with LinuxController::with_manager and a GeneralManager describing this system's \
+             commands instead (e.g. SysVinit's \"service <name> start\"/\"update-rc.d\")",
+        )
+    }
+}
+
+impl SystemServiceManager for UnknownInitManager {
+    fn install(
+        &self,
+        _service_name: &str,
+        _display_name: &str,
+        _exec_start: &str,
+        _config: Option<&str>,
+    ) -> Result<(), Error> {
+        Err(Self::error())
+    }
+
+    fn uninstall(&self, _service_name: &str) -> Result<(), Error> {
+        Err(Self::error())
+    }
+
+    fn start(&self, _service_name: &str) -> Result<(), Error> {
+        Err(Self::error())
+    }
+
+    fn stop(&self, _service_name: &str) -> Result<(), Error> {
+        Err(Self::error())
+    }
+
+    fn status(&self, _service_name: &str) -> Result<ServiceStatus, Error> {
+        Err(Self::error())
+    }
+}
+
+fn which(command: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| {
+            env::split_paths(&paths).any(|dir| dir.join(command).is_file())
+        })
+        .unwrap_or(false)
+}
+
+/// Wraps `arg` in single quotes for embedding in the shell-interpreted
+/// `ExecStart=`/`command=` lines the init backends render, escaping any
+/// single quote it contains.
+fn quote_shell_arg(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r#"'\''"#))
+}
+
+pub struct LinuxController {
+    pub service_name: String,
+    pub display_name: String,
+    pub description: String,
+    pub config: Option<String>,
+    manager: Box<dyn SystemServiceManager>,
+    launch_args: Vec<String>,
+}
+
+impl LinuxController {
+    pub fn new(service_name: &str, display_name: &str, description: &str) -> LinuxController {
+        LinuxController {
+            service_name: service_name.to_string(),
+            display_name: display_name.to_string(),
+            description: description.to_string(),
+            config: None,
+            manager: detect_service_manager(),
+            launch_args: Vec::new(),
+        }
+    }
+
+    /// Builds a controller against a specific [`SystemServiceManager`],
+    /// bypassing auto-detection of the live init system.
+    pub fn with_manager(
+        service_name: &str,
+        display_name: &str,
+        description: &str,
+        manager: Box<dyn SystemServiceManager>,
+    ) -> LinuxController {
+        LinuxController {
+            service_name: service_name.to_string(),
+            display_name: display_name.to_string(),
+            description: description.to_string(),
+            config: None,
+            manager,
+            launch_args: Vec::new(),
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        service_main_wrapper: LinuxServiceMainWrapperFn,
+    ) -> Result<(), Error> {
+        service_main_wrapper(env::args().collect());
+        Ok(())
+    }
+
+    /// Switches to a per-user systemd unit (`systemctl --user`, under
+    /// `$XDG_CONFIG_HOME/systemd/user/`) instead of whatever system-wide
+    /// init system was auto-detected, so the service can be installed and
+    /// run without root.
+    pub fn user_scope(&mut self) -> &mut Self {
+        self.manager = Box::new(SystemdManager::user_scope());
+        self
+    }
+
+    fn get_exec_start(&self) -> Result<String, Error> {
+        let exe = fs::read_link("/proc/self/exe")
+            .map_err(|e| Error::new(&format!("Failed to read /proc/self/exe: {}", e)))?
+            .to_str()
+            .ok_or("Failed to parse /proc/self/exe")?
+            .to_string();
+
+        if self.launch_args.is_empty() {
+            return Ok(exe);
+        }
+
+        let args = self
+            .launch_args
+            .iter()
+            .map(|arg| quote_shell_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(format!("{} {}", quote_shell_arg(&exe), args))
+    }
+}
+
+impl ControllerInterface for LinuxController {
+    fn create(&mut self) -> Result<(), Error> {
+        let exec_start = self.get_exec_start()?;
+        self.manager.install(
+            &self.service_name,
+            &self.display_name,
+            &exec_start,
+            self.config.as_deref(),
+        )
+    }
+
+    fn delete(&mut self) -> Result<(), Error> {
+        self.manager.uninstall(&self.service_name)?;
+
+        if let Ok(exe) = env::current_exe() {
+            super::launch_args::delete(&exe);
+        }
+
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<(), Error> {
+        self.manager.start(&self.service_name)
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        self.manager.stop(&self.service_name)
+    }
+
+    fn create_with_args(&mut self, args: &[String]) -> Result<(), Error> {
+        self.launch_args = args.to_vec();
+        super::save_launch_args(args)?;
+        self.create()
+    }
+}
+
+impl LinuxController {
+    pub fn get_status(&self) -> Result<ServiceStatus, Error> {
+        self.manager.status(&self.service_name)
+    }
+}
+
+fn run_monitor<T: Send + 'static>(
+    tx: mpsc::Sender<ServiceEvent<T>>,
+) -> Result<Monitor, std::io::Error> {
+    let monitor = Monitor::new()?;
+
+    let mut current_session = match login_session::get_active_session() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            debug!("Failed to get active session {}", e);
+            None
+        }
+    };
+
+    monitor.init(Category::Sessions, move || {
+        let active_session = match login_session::get_active_session() {
+            Ok(s) => Some(s),
+            Err(e) => {
+                debug!("Failed to get active session {}", e);
+                None
+            }
+        };
+
+        let session_changed = match (&current_session, &active_session) {
+            (Some(current_session), Some(active_session)) => current_session != active_session,
+            (None, None) => false,
+            _ => true,
+        };
+
+        if session_changed {
+            if let Some(active_session) = active_session.as_ref() {
+                let _ = tx.send(ServiceEvent::SessionConnect(Session::new(
+                    active_session.identifier.to_string(),
+                )));
+            }
+
+            if let Some(current_session) = current_session.as_ref() {
+                let _ = tx.send(ServiceEvent::SessionDisconnect(Session::new(
+                    current_session.identifier.to_string(),
+                )));
+            }
+        }
+
+        current_session = active_session;
+    })?;
+
+    Ok(monitor)
+}
+
+#[macro_export]
+macro_rules! Service {
+    ($name:expr, $function:ident) => {
+        extern "system" fn service_main_wrapper(args: Vec<String>) {
+            dispatch($function, args);
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn dispatch<T: Send + 'static>(service_main: ServiceMainFn<T>, mut args: Vec<String>) {
+    // `register` passes the process argv, which already holds the args
+    // rendered into the unit's start command; only append the saved ones
+    // when they aren't there yet.
+    if let Ok(exe) = env::current_exe() {
+        let saved = super::launch_args::load(&exe);
+        if !args.ends_with(&saved) {
+            args.extend(saved);
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let (status_tx, status_rx) = mpsc::channel();
+
+    let _monitor = run_monitor(tx.clone()).expect("Failed to run session monitor");
+    let _tx = tx.clone();
+
+    ctrlc::set_handler(move || {
+        let _ = tx.send(ServiceEvent::Stop);
+    })
+    .expect("Failed to register Ctrl-C handler");
+
+    // There is no service manager to report checkpoint progress to, so just
+    // trace it; the values still flow through the same contract as Windows.
+    std::thread::spawn(move || {
+        while let Ok(update) = status_rx.recv() {
+            debug!(
+                "stop checkpoint {} (wait_hint {:?})",
+                update.checkpoint, update.wait_hint
+            );
+        }
+    });
+
+    match service_main(rx, _tx, status_tx, args, false) {
+        ServiceResult::Success => {}
+        ServiceResult::ServiceSpecific(code) => {
+            // There's no SCM to hand a structured exit code to, so reflect
+            // it the way systemd (Restart=/failure reporting) and every
+            // other init system actually observe failure: the process exit
+            // code. Exiting 0 here would report a crashed service as a
+            // clean stop.
+            debug!("service exited with service-specific code {}", code);
+            std::process::exit(code as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn which_finds_a_command_known_to_be_on_path() {
+        assert!(which("sh"));
+    }
+
+    #[test]
+    fn which_rejects_an_unknown_command() {
+        assert!(!which("ceviche-rs-definitely-not-a-real-command"));
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs::{self, read, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{debug, info};
+
+use crate::Error;
+
+use super::{ActiveState, InactiveState, ServiceState, ServiceStatus, SystemServiceManager};
+
+fn rc_service(service_name: &str, args: &[&str]) -> Result<String, Error> {
+    let mut process = Command::new("rc-service");
+    process.arg(service_name).args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute rc-service: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(&format!(
+            "rc-service {} {:?} failed: {}",
+            service_name,
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Runs `rc-service <service_name> <args>` and returns its stdout regardless
+/// of exit code. `rc-service status` follows the rc.subr convention of
+/// exiting non-zero for "stopped"/"crashed", so only a genuine failure to
+/// spawn the process is an `Err` here.
+fn rc_service_status(service_name: &str, args: &[&str]) -> Result<String, Error> {
+    let mut process = Command::new("rc-service");
+    process.arg(service_name).args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute rc-service: {}", e)))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn rc_update(args: &[&str]) -> Result<(), Error> {
+    let mut process = Command::new("rc-update");
+    process.args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute rc-update: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(&format!(
+            "rc-update {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Drives an OpenRC service through its `/etc/init.d` script and the
+/// `rc-service`/`rc-update` commands.
+#[derive(Default)]
+pub struct OpenRcManager;
+
+impl OpenRcManager {
+    fn get_script_path(&self, service_name: &str) -> PathBuf {
+        Path::new("/etc/init.d/").join(service_name)
+    }
+
+    fn get_pid_path(&self, service_name: &str) -> PathBuf {
+        Path::new("/run/").join(format!("{}.pid", service_name))
+    }
+
+    fn get_script_content(&self, service_name: &str, exec_start: &str) -> String {
+        format!(
+            r#"#!/sbin/openrc-run
+
+name="{name}"
+command="{exec_start}"
+command_background="yes"
+pidfile="/run/{name}.pid"
+"#,
+            name = service_name,
+            exec_start = exec_start,
+        )
+    }
+}
+
+impl SystemServiceManager for OpenRcManager {
+    fn install(
+        &self,
+        service_name: &str,
+        _display_name: &str,
+        exec_start: &str,
+        config: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = self.get_script_path(service_name);
+        let content = self.get_script_content(service_name, exec_start);
+        info!("Writing init script {}", path.display());
+        File::create(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+
+        let mut perms = fs::metadata(&path)
+            .map_err(|e| Error::new(&format!("Failed to stat {}: {}", path.display(), e)))?
+            .permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut perms, 0o755);
+        fs::set_permissions(&path, perms)
+            .map_err(|e| Error::new(&format!("Failed to chmod {}: {}", path.display(), e)))?;
+
+        if let Some(config) = config {
+            let path = Path::new("/etc/conf.d/").join(service_name);
+            info!("Writing config file {}", path.display());
+            File::create(&path)
+                .and_then(|mut file| file.write_all(config.as_bytes()))
+                .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+
+        rc_update(&["add", service_name, "default"])
+    }
+
+    fn uninstall(&self, service_name: &str) -> Result<(), Error> {
+        rc_update(&["del", service_name, "default"])
+            .map_err(|e| debug!("{}", e))
+            .ok();
+
+        let path = self.get_script_path(service_name);
+        fs::remove_file(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        let path = Path::new("/etc/conf.d/").join(service_name);
+        fs::remove_file(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        Ok(())
+    }
+
+    fn start(&self, service_name: &str) -> Result<(), Error> {
+        rc_service(service_name, &["start"]).map(|_| ())
+    }
+
+    fn stop(&self, service_name: &str) -> Result<(), Error> {
+        rc_service(service_name, &["stop"]).map(|_| ())
+    }
+
+    fn status(&self, service_name: &str) -> Result<ServiceStatus, Error> {
+        let result = rc_service_status(service_name, &["status"])?;
+
+        let pid = read(self.get_pid_path(service_name))
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .unwrap_or(0);
+
+        let cmdline = if pid != 0 {
+            read(&format!("/proc/{}/cmdline", pid))
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let is_failed = result.contains("crashed");
+        let state = if result.contains("started") {
+            ServiceState::Active(ActiveState::Running)
+        } else if is_failed {
+            ServiceState::Active(ActiveState::Dead)
+        } else if result.contains("stopped") {
+            ServiceState::Inactive(InactiveState::Dead)
+        } else {
+            return Err(Error::new(&format!("Invalid rc-service status: {}", result)));
+        };
+
+        Ok(ServiceStatus {
+            state,
+            cmdline,
+            pid,
+            is_failed,
+            details: result,
+        })
+    }
+}
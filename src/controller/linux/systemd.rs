@@ -0,0 +1,249 @@
+use std::env;
+use std::fs::{self, read, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{debug, info};
+
+use crate::Error;
+
+use super::{ActiveState, InactiveState, ServiceState, ServiceStatus, SystemServiceManager};
+
+fn systemctl_execute_with_result(user_scope: bool, args: &[&str]) -> Result<String, Error> {
+    let mut process = Command::new("systemctl");
+    if user_scope {
+        process.arg("--user");
+    }
+    process.args(args);
+
+    let output = process
+        .output()
+        .map_err(|e| Error::new(&format!("Failed to execute command {}: {}", args[0], e)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(&format!(
+            "Command \"{}\" failed ({}): {}",
+            args[0],
+            output.status.code().expect("Process terminated by signal"),
+            std::str::from_utf8(&output.stderr).unwrap_or_default()
+        )));
+    }
+
+    if !output.stdout.is_empty() {
+        info!("{}", String::from_utf8_lossy(&output.stdout));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn systemctl_execute(user_scope: bool, args: &[&str]) -> Result<(), Error> {
+    systemctl_execute_with_result(user_scope, args).map(|_| ())
+}
+
+fn xdg_config_home() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&env::var("HOME").unwrap_or_default()).join(".config"))
+}
+
+/// Drives a systemd unit through `systemctl`, either a system-wide unit
+/// under `/lib/systemd/system/` or, when `user_scope` is set, a per-user
+/// unit under `$XDG_CONFIG_HOME/systemd/user/` managed with
+/// `systemctl --user`.
+#[derive(Default)]
+pub struct SystemdManager {
+    user_scope: bool,
+}
+
+impl SystemdManager {
+    /// Drives a per-user systemd unit instead of a system-wide one, so the
+    /// service can be installed and started without root.
+    pub fn user_scope() -> SystemdManager {
+        SystemdManager { user_scope: true }
+    }
+
+    fn get_service_file_name(&self, service_name: &str) -> String {
+        format!("{}.service", service_name)
+    }
+
+    fn get_unit_dir(&self) -> PathBuf {
+        if self.user_scope {
+            xdg_config_home().join("systemd/user")
+        } else {
+            PathBuf::from("/lib/systemd/system/")
+        }
+    }
+
+    fn get_service_unit_path(&self, service_name: &str) -> PathBuf {
+        self.get_unit_dir().join(self.get_service_file_name(service_name))
+    }
+
+    fn get_service_dropin_dir(&self, service_name: &str) -> PathBuf {
+        self.get_unit_dir()
+            .join(format!("{}.d", self.get_service_file_name(service_name)))
+    }
+
+    fn get_service_unit_content(&self, service_name: &str, exec_start: &str) -> String {
+        format!(
+            r#"
+[Unit]
+Description={}
+
+[Service]
+ExecStart={}
+
+[Install]
+WantedBy={}"#,
+            service_name,
+            exec_start,
+            if self.user_scope {
+                "default.target"
+            } else {
+                "multi-user.target"
+            }
+        )
+    }
+
+    /// Lets the per-user systemd instance keep running after the user logs
+    /// out, so a user-scope service survives logout like a system one
+    /// would.
+    fn enable_lingering(&self) -> Result<(), Error> {
+        let user = env::var("USER")
+            .map_err(|e| Error::new(&format!("Failed to read USER: {}", e)))?;
+        let mut process = Command::new("loginctl");
+        process.args(["enable-linger", &user]);
+        process
+            .output()
+            .map_err(|e| Error::new(&format!("Failed to execute loginctl: {}", e)))?;
+        Ok(())
+    }
+}
+
+impl SystemServiceManager for SystemdManager {
+    fn install(
+        &self,
+        service_name: &str,
+        _display_name: &str,
+        exec_start: &str,
+        config: Option<&str>,
+    ) -> Result<(), Error> {
+        let unit_dir = self.get_unit_dir();
+        fs::create_dir_all(&unit_dir)
+            .map_err(|e| Error::new(&format!("Failed to create {}: {}", unit_dir.display(), e)))?;
+
+        let path = self.get_service_unit_path(service_name);
+        let content = self.get_service_unit_content(service_name, exec_start);
+        info!("Writing service file {}", path.display());
+        File::create(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+
+        if let Some(config) = config {
+            let dropin_dir = self.get_service_dropin_dir(service_name);
+            let path = dropin_dir.join(format!("{}.conf", service_name));
+            fs::create_dir(&dropin_dir)
+                .map_err(|e| Error::new(&format!("Failed to create {}: {}", dropin_dir.display(), e)))?;
+            info!("Writing config file {}", path.display());
+            File::create(&path)
+                .and_then(|mut file| file.write_all(config.as_bytes()))
+                .map_err(|e| Error::new(&format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+
+        if self.user_scope {
+            self.enable_lingering()
+                .map_err(|e| debug!("Failed to enable lingering: {}", e))
+                .ok();
+        }
+
+        systemctl_execute(self.user_scope, &["daemon-reload"])?;
+        systemctl_execute(self.user_scope, &["enable", service_name])
+    }
+
+    fn uninstall(&self, service_name: &str) -> Result<(), Error> {
+        systemctl_execute(self.user_scope, &["disable", service_name])?;
+        systemctl_execute(self.user_scope, &["daemon-reload"])
+            .map_err(|e| debug!("{}", e))
+            .ok();
+        systemctl_execute(self.user_scope, &["reset-failed"])
+            .map_err(|e| debug!("{}", e))
+            .ok();
+
+        let path = self.get_service_unit_path(service_name);
+        fs::remove_file(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        let path = self.get_service_dropin_dir(service_name);
+        fs::remove_dir_all(&path)
+            .map_err(|e| debug!("Failed to delete {}: {}", path.display(), e))
+            .ok();
+
+        Ok(())
+    }
+
+    fn start(&self, service_name: &str) -> Result<(), Error> {
+        systemctl_execute(self.user_scope, &["start", service_name])
+    }
+
+    fn stop(&self, service_name: &str) -> Result<(), Error> {
+        systemctl_execute(self.user_scope, &["stop", service_name])
+    }
+
+    fn status(&self, service_name: &str) -> Result<ServiceStatus, Error> {
+        let pid = systemctl_execute_with_result(self.user_scope, &["show", "-p", "MainPID", service_name])?
+            .trim_start_matches("MainPID=")
+            .trim()
+            .parse::<u32>()
+            .unwrap();
+
+        let is_failed = if let Ok(ret) =
+            systemctl_execute_with_result(self.user_scope, &["is-failed", service_name])
+        {
+            ret.contains("failed")
+        } else {
+            false
+        };
+
+        let cmdline = String::from_utf8(read(&format!("/proc/{}/cmdline", pid)).unwrap()).unwrap();
+
+        let result = systemctl_execute_with_result(self.user_scope, &["status", service_name])?;
+        if result.contains("active (") {
+            Ok(ServiceStatus {
+                state: if result.contains(" (running)") {
+                    ServiceState::Active(ActiveState::Running)
+                } else if result.contains(" (exited)") {
+                    ServiceState::Active(ActiveState::Exited)
+                } else if result.contains(" (waiting)") {
+                    ServiceState::Active(ActiveState::Waiting)
+                } else if result.contains(" (dead)") {
+                    ServiceState::Active(ActiveState::Dead)
+                } else {
+                    return Err(Error::new(&format!("Invalid ActiveState : {}", result)));
+                },
+                details: result,
+                cmdline,
+                pid,
+                is_failed,
+            })
+        } else {
+            Ok(ServiceStatus {
+                state: if result.contains(" (dead)") {
+                    ServiceState::Inactive(InactiveState::Dead)
+                } else if result.contains(" (exited)") {
+                    ServiceState::Inactive(InactiveState::Exited)
+                } else if result.contains(" (waiting)") {
+                    ServiceState::Inactive(InactiveState::Waiting)
+                } else if result.contains(" (resetting)") {
+                    ServiceState::Inactive(InactiveState::Resetting)
+                } else {
+                    return Err(Error::new(&format!("Invalid ActiveState : {}", result)));
+                },
+                details: result,
+                cmdline,
+                pid,
+                is_failed,
+            })
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::Error;
+use crate::ServiceEvent;
+use std::sync::mpsc;
+use std::time::Duration;
+
+mod launch_args;
+
+/// Functions implemented by a controller to create, remove, start and stop
+/// the service on the system.
+pub trait ControllerInterface {
+    fn create(&mut self) -> Result<(), Error>;
+    fn delete(&mut self) -> Result<(), Error>;
+    fn start(&mut self) -> Result<(), Error>;
+    fn stop(&mut self) -> Result<(), Error>;
+
+    /// Like `create`, but renders `args` into the service description
+    /// itself (systemd's `ExecStart=`, the Windows service's binary path)
+    /// so tooling that inspects the unit/service (`systemctl cat`, `sc qc`)
+    /// shows the real effective command line, and also persists them to a
+    /// sidecar file next to the service executable so every future start
+    /// picks them back up through `dispatch`, which appends them unless the
+    /// arguments the service was started with already end with them.
+    fn create_with_args(&mut self, args: &[String]) -> Result<(), Error>;
+}
+
+/// Shared by platform controllers' `create_with_args`: saves `args` to the
+/// sidecar file next to the current executable.
+fn save_launch_args(args: &[String]) -> Result<(), Error> {
+    let exe = std::env::current_exe()
+        .map_err(|e| Error::new(&format!("Failed to get current executable: {}", e)))?;
+    launch_args::save(&exe, args)
+}
+
+/// Surface every platform's `ServiceStatus` type exposes, so callers that
+/// only care about the basics don't need to match on platform-specific state
+/// enums.
+pub trait BasicServiceStatus {
+    fn is_running(&self) -> bool;
+    fn is_failed(&self) -> bool;
+    fn get_cmdline(&self) -> &str;
+}
+
+/// A progress update a service main sends while handling a (potentially
+/// slow) stop, so the platform controller can keep reporting forward
+/// progress instead of letting the operation look hung.
+#[derive(Debug, Clone)]
+pub struct StatusUpdate {
+    pub checkpoint: u32,
+    pub wait_hint: Duration,
+}
+
+/// What a service main returned when it exited.
+#[derive(Debug)]
+pub enum ServiceResult {
+    /// The service stopped cleanly.
+    Success,
+    /// The service stopped because of a service-defined failure, reported
+    /// to the platform as a specific exit code where that is supported
+    /// (`ERROR_SERVICE_SPECIFIC_ERROR` with the code in
+    /// `dwServiceSpecificExitCode` on Windows) instead of a generic failure.
+    ServiceSpecific(u32),
+}
+
+pub type ServiceMainFn<T> = fn(
+    mpsc::Receiver<ServiceEvent<T>>,
+    mpsc::Sender<ServiceEvent<T>>,
+    mpsc::Sender<StatusUpdate>,
+    Vec<String>,
+    bool,
+) -> ServiceResult;
+
+cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        mod windows;
+        pub use self::windows::*;
+        pub use self::windows::WindowsController as Controller;
+    } else if #[cfg(target_os = "linux")] {
+        mod linux;
+        pub use self::linux::*;
+        pub use self::linux::LinuxController as Controller;
+    }
+}
@@ -0,0 +1,468 @@
+use std::ffi::{OsStr, OsString};
+use std::mem;
+use std::os::raw::c_void;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::ptr;
+use std::sync::mpsc;
+
+use log::{debug, info};
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::ntdef::LPWSTR;
+use winapi::shared::winerror::{ERROR_SERVICE_DOES_NOT_EXIST, NO_ERROR};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::winnt::{DELETE, SERVICE_QUERY_STATUS, SERVICE_START, SERVICE_STOP};
+use winapi::um::winsvc::{
+    self, CloseServiceHandle, ControlService, CreateServiceW, DeleteService, OpenSCManagerW,
+    OpenServiceW, QueryServiceStatusEx, RegisterServiceCtrlHandlerExW, SetServiceStatus,
+    StartServiceCtrlDispatcherW, StartServiceW, SC_HANDLE, SC_MANAGER_ALL_ACCESS,
+    SC_STATUS_PROCESS_INFO, SERVICE_ACCEPT_PAUSE_CONTINUE, SERVICE_ACCEPT_STOP,
+    SERVICE_AUTO_START, SERVICE_CONTROL_CONTINUE, SERVICE_CONTROL_PAUSE, SERVICE_CONTROL_STOP,
+    SERVICE_ERROR_NORMAL, SERVICE_RUNNING, SERVICE_START_PENDING, SERVICE_STATUS,
+    SERVICE_STATUS_HANDLE, SERVICE_STATUS_PROCESS, SERVICE_STOPPED, SERVICE_STOP_PENDING,
+    SERVICE_TABLE_ENTRYW, SERVICE_WIN32_OWN_PROCESS,
+};
+
+use crate::controller::{BasicServiceStatus, ControllerInterface, ServiceMainFn, ServiceResult};
+use crate::Error;
+use crate::ServiceEvent;
+
+pub use crate::session::Session;
+
+type WindowsServiceMainWrapperFn = extern "system" fn(args: Vec<String>);
+
+// The SCM only gives `StartServiceCtrlDispatcherW` a single, plain
+// `extern "system" fn(argc, argv)` slot, so the user's `Vec<String>`-taking
+// wrapper can't go in there directly. Stash it here and have
+// `service_main_shim`, whose signature matches `LPSERVICE_MAIN_FUNCTIONW`,
+// decode `argv` and forward to it.
+static mut SERVICE_MAIN_WRAPPER: Option<WindowsServiceMainWrapperFn> = None;
+
+unsafe extern "system" fn service_main_shim(argc: DWORD, argv: *mut LPWSTR) {
+    let args = decode_service_args(argc, argv);
+    if let Some(wrapper) = SERVICE_MAIN_WRAPPER {
+        wrapper(args);
+    }
+}
+
+unsafe fn decode_service_args(argc: DWORD, argv: *mut LPWSTR) -> Vec<String> {
+    if argv.is_null() {
+        return Vec::new();
+    }
+
+    (0..argc as isize)
+        .map(|i| {
+            let arg = *argv.offset(i);
+            let len = (0isize..).take_while(|&j| *arg.offset(j) != 0).count();
+            let wide = std::slice::from_raw_parts(arg, len);
+            OsString::from_wide(wide).to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+fn to_wstring(s: &str) -> Vec<u16> {
+    OsStr::new(s)
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>()
+}
+
+fn last_error(action: &str) -> Error {
+    Error::new(&format!(
+        "{} failed (GetLastError = {})",
+        action,
+        unsafe { GetLastError() }
+    ))
+}
+
+struct ServiceHandle(SC_HANDLE);
+
+impl Drop for ServiceHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseServiceHandle(self.0);
+        }
+    }
+}
+
+fn open_sc_manager() -> Result<ServiceHandle, Error> {
+    let handle =
+        unsafe { OpenSCManagerW(ptr::null(), ptr::null(), SC_MANAGER_ALL_ACCESS) };
+    if handle.is_null() {
+        return Err(last_error("OpenSCManager"));
+    }
+    Ok(ServiceHandle(handle))
+}
+
+fn open_service(manager: &ServiceHandle, service_name: &str, access: DWORD) -> Result<ServiceHandle, Error> {
+    let handle = unsafe { OpenServiceW(manager.0, to_wstring(service_name).as_ptr(), access) };
+    if handle.is_null() {
+        return Err(last_error("OpenService"));
+    }
+    Ok(ServiceHandle(handle))
+}
+
+#[derive(Debug)]
+pub enum ServiceState {
+    Stopped,
+    StartPending,
+    StopPending,
+    Running,
+    ContinuePending,
+    PausePending,
+    Paused,
+}
+
+#[derive(Debug)]
+pub struct ServiceStatus {
+    pub state: ServiceState,
+    pub pid: u32,
+    pub win32_exit_code: u32,
+    pub cmdline: String,
+}
+
+impl BasicServiceStatus for ServiceStatus {
+    fn is_running(&self) -> bool {
+        matches!(self.state, ServiceState::Running)
+    }
+
+    fn is_failed(&self) -> bool {
+        self.win32_exit_code != 0
+    }
+
+    fn get_cmdline(&self) -> &str {
+        &self.cmdline
+    }
+}
+
+/// Wraps `arg` in double quotes for the service's `binPathName` command
+/// line, if it contains whitespace, escaping any double quote it contains.
+fn quote_windows_arg(arg: &str) -> String {
+    if arg.chars().any(char::is_whitespace) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+pub struct WindowsController {
+    pub service_name: String,
+    pub display_name: String,
+    pub description: String,
+    pub config: Option<String>,
+    launch_args: Vec<String>,
+}
+
+impl WindowsController {
+    pub fn new(service_name: &str, display_name: &str, description: &str) -> WindowsController {
+        WindowsController {
+            service_name: service_name.to_string(),
+            display_name: display_name.to_string(),
+            description: description.to_string(),
+            config: None,
+            launch_args: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, service_main_wrapper: WindowsServiceMainWrapperFn) -> Result<(), Error> {
+        let name = to_wstring(&self.service_name);
+
+        // SAFETY: `StartServiceCtrlDispatcherW` below blocks until the
+        // service stops, so there's no concurrent access to this static
+        // from another registration while it's in use.
+        unsafe {
+            SERVICE_MAIN_WRAPPER = Some(service_main_wrapper);
+        }
+
+        let service_table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: name.as_ptr() as _,
+                lpServiceProc: Some(service_main_shim),
+            },
+            unsafe { mem::zeroed() },
+        ];
+
+        if unsafe { StartServiceCtrlDispatcherW(service_table.as_ptr()) } == 0 {
+            return Err(last_error("StartServiceCtrlDispatcher"));
+        }
+
+        Ok(())
+    }
+
+    fn get_binary_path(&self) -> Result<String, Error> {
+        let exe = std::env::current_exe()
+            .map_err(|e| Error::new(&format!("Failed to get current executable: {}", e)))?;
+        let exe = quote_windows_arg(&exe.to_string_lossy());
+
+        if self.launch_args.is_empty() {
+            return Ok(exe);
+        }
+
+        let args = self
+            .launch_args
+            .iter()
+            .map(|arg| quote_windows_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(format!("{} {}", exe, args))
+    }
+}
+
+impl ControllerInterface for WindowsController {
+    fn create(&mut self) -> Result<(), Error> {
+        let manager = open_sc_manager()?;
+        let binary_path = self.get_binary_path()?;
+
+        let handle = unsafe {
+            CreateServiceW(
+                manager.0,
+                to_wstring(&self.service_name).as_ptr(),
+                to_wstring(&self.display_name).as_ptr(),
+                SERVICE_QUERY_STATUS | SERVICE_START | SERVICE_STOP,
+                SERVICE_WIN32_OWN_PROCESS,
+                SERVICE_AUTO_START,
+                SERVICE_ERROR_NORMAL,
+                to_wstring(&binary_path).as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+            )
+        };
+
+        if handle.is_null() {
+            return Err(last_error("CreateService"));
+        }
+
+        unsafe {
+            CloseServiceHandle(handle);
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self) -> Result<(), Error> {
+        let manager = open_sc_manager()?;
+        let service = open_service(&manager, &self.service_name, DELETE)?;
+
+        if unsafe { DeleteService(service.0) } == 0 {
+            return Err(last_error("DeleteService"));
+        }
+
+        if let Ok(exe) = std::env::current_exe() {
+            super::launch_args::delete(&exe);
+        }
+
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<(), Error> {
+        let manager = open_sc_manager()?;
+        let service = open_service(&manager, &self.service_name, SERVICE_START)?;
+
+        if unsafe { StartServiceW(service.0, 0, ptr::null_mut()) } == 0 {
+            return Err(last_error("StartService"));
+        }
+
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        let manager = open_sc_manager()?;
+        let service = open_service(&manager, &self.service_name, SERVICE_STOP)?;
+
+        let mut status: SERVICE_STATUS_PROCESS = unsafe { mem::zeroed() };
+        if unsafe {
+            ControlService(
+                service.0,
+                SERVICE_CONTROL_STOP,
+                &mut status as *mut _ as *mut winsvc::SERVICE_STATUS,
+            )
+        } == 0
+        {
+            return Err(last_error("ControlService"));
+        }
+
+        Ok(())
+    }
+
+    fn create_with_args(&mut self, args: &[String]) -> Result<(), Error> {
+        self.launch_args = args.to_vec();
+        super::save_launch_args(args)?;
+        self.create()
+    }
+}
+
+impl WindowsController {
+    pub fn get_status(&self) -> Result<ServiceStatus, Error> {
+        let manager = open_sc_manager()?;
+        let service = open_service(&manager, &self.service_name, SERVICE_QUERY_STATUS)?;
+
+        let mut status: SERVICE_STATUS_PROCESS = unsafe { mem::zeroed() };
+        let mut bytes_needed: DWORD = 0;
+
+        let ok = unsafe {
+            QueryServiceStatusEx(
+                service.0,
+                SC_STATUS_PROCESS_INFO,
+                &mut status as *mut _ as *mut u8,
+                mem::size_of::<SERVICE_STATUS_PROCESS>() as DWORD,
+                &mut bytes_needed,
+            )
+        };
+
+        if ok == 0 {
+            if unsafe { GetLastError() } == ERROR_SERVICE_DOES_NOT_EXIST {
+                return Err(Error::new("Service does not exist"));
+            }
+            return Err(last_error("QueryServiceStatusEx"));
+        }
+
+        let state = match status.dwCurrentState {
+            winsvc::SERVICE_STOPPED => ServiceState::Stopped,
+            winsvc::SERVICE_START_PENDING => ServiceState::StartPending,
+            winsvc::SERVICE_STOP_PENDING => ServiceState::StopPending,
+            winsvc::SERVICE_RUNNING => ServiceState::Running,
+            winsvc::SERVICE_CONTINUE_PENDING => ServiceState::ContinuePending,
+            winsvc::SERVICE_PAUSE_PENDING => ServiceState::PausePending,
+            winsvc::SERVICE_PAUSED => ServiceState::Paused,
+            _ => return Err(Error::new("Invalid service state")),
+        };
+
+        Ok(ServiceStatus {
+            state,
+            pid: status.dwProcessId,
+            win32_exit_code: status.dwWin32ExitCode,
+            cmdline: self.get_binary_path().unwrap_or_default(),
+        })
+    }
+}
+
+struct HandlerContext<T> {
+    tx: mpsc::Sender<ServiceEvent<T>>,
+}
+
+extern "system" fn service_ctrl_handler_ex<T: Send + 'static>(
+    control: DWORD,
+    _event_type: DWORD,
+    _event_data: *mut c_void,
+    context: *mut c_void,
+) -> DWORD {
+    let ctx = unsafe { &*(context as *const HandlerContext<T>) };
+    match control {
+        SERVICE_CONTROL_STOP => {
+            let _ = ctx.tx.send(ServiceEvent::Stop);
+        }
+        SERVICE_CONTROL_PAUSE => {
+            let _ = ctx.tx.send(ServiceEvent::Pause);
+        }
+        SERVICE_CONTROL_CONTINUE => {
+            let _ = ctx.tx.send(ServiceEvent::Continue);
+        }
+        _ => {}
+    }
+    NO_ERROR
+}
+
+fn set_status(handle: SERVICE_STATUS_HANDLE, current_state: DWORD, checkpoint: DWORD, wait_hint: DWORD, exit_code: DWORD, service_specific_exit_code: DWORD) {
+    let mut status: SERVICE_STATUS = unsafe { mem::zeroed() };
+    status.dwServiceType = SERVICE_WIN32_OWN_PROCESS;
+    status.dwCurrentState = current_state;
+    status.dwControlsAccepted = if current_state == SERVICE_START_PENDING {
+        0
+    } else {
+        SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_PAUSE_CONTINUE
+    };
+    status.dwWin32ExitCode = exit_code;
+    status.dwServiceSpecificExitCode = service_specific_exit_code;
+    status.dwCheckPoint = checkpoint;
+    status.dwWaitHint = wait_hint;
+
+    unsafe {
+        SetServiceStatus(handle, &mut status);
+    }
+}
+
+#[doc(hidden)]
+pub fn dispatch<T: Send + 'static>(service_main: ServiceMainFn<T>, mut args: Vec<String>) {
+    if let Ok(exe) = std::env::current_exe() {
+        args.extend(super::launch_args::load(&exe));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let (status_tx, status_rx) = mpsc::channel();
+    let _tx = tx.clone();
+
+    let context = Box::into_raw(Box::new(HandlerContext { tx: tx.clone() }));
+
+    let handle = unsafe {
+        RegisterServiceCtrlHandlerExW(
+            to_wstring("").as_ptr(),
+            Some(service_ctrl_handler_ex::<T>),
+            context as *mut c_void,
+        )
+    };
+
+    if handle.is_null() {
+        debug!("RegisterServiceCtrlHandlerEx failed, running without SCM status reporting");
+    } else {
+        set_status(handle, SERVICE_START_PENDING, 0, 3000, NO_ERROR, 0);
+        set_status(handle, SERVICE_RUNNING, 0, 0, NO_ERROR, 0);
+    }
+
+    // Forward checkpoint/wait_hint updates the service main reports while
+    // handling a (potentially slow) stop, so the SCM doesn't time it out.
+    // SERVICE_STATUS_HANDLE isn't Send, so it crosses the thread boundary as
+    // a plain integer and is cast back on the other side.
+    let status_handle_for_updates = handle as usize;
+    std::thread::spawn(move || {
+        let handle = status_handle_for_updates as SERVICE_STATUS_HANDLE;
+        while let Ok(update) = status_rx.recv() {
+            if !handle.is_null() {
+                set_status(
+                    handle,
+                    SERVICE_STOP_PENDING,
+                    update.checkpoint,
+                    update.wait_hint.as_millis() as DWORD,
+                    NO_ERROR,
+                    0,
+                );
+            }
+        }
+    });
+
+    info!("Dispatching service main");
+    debug!("args: {:?}", args);
+
+    let result = service_main(rx, _tx, status_tx, args, false);
+
+    if !handle.is_null() {
+        match result {
+            ServiceResult::Success => set_status(handle, SERVICE_STOPPED, 0, 0, NO_ERROR, 0),
+            ServiceResult::ServiceSpecific(code) => {
+                set_status(
+                    handle,
+                    SERVICE_STOPPED,
+                    0,
+                    0,
+                    winapi::shared::winerror::ERROR_SERVICE_SPECIFIC_ERROR,
+                    code,
+                );
+            }
+        }
+    }
+
+    unsafe {
+        drop(Box::from_raw(context));
+    }
+}
+
+#[macro_export]
+macro_rules! Service {
+    ($name:expr, $function:ident) => {
+        extern "system" fn service_main_wrapper(args: Vec<String>) {
+            dispatch($function, args);
+        }
+    };
+}
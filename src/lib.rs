@@ -12,17 +12,26 @@
 //! fn my_service_main(
 //!     rx: mpsc::Receiver<ServiceEvent<CustomServiceEvent>>,
 //!     _tx: mpsc::Sender<ServiceEvent<CustomServiceEvent>>,
+//!     status_tx: mpsc::Sender<StatusUpdate>,
 //!     args: Vec<String>,
-//!     standalone_mode: bool) -> u32 {
+//!     standalone_mode: bool) -> ServiceResult {
 //!    loop {
 //!        if let Ok(control_code) = rx.recv() {
 //!            match control_code {
-//!                ServiceEvent::Stop => break,
+//!                ServiceEvent::Stop => {
+//!                    // Report progress while shutting down so a slow stop
+//!                    // doesn't trip the platform's timeout.
+//!                    let _ = status_tx.send(StatusUpdate {
+//!                        checkpoint: 1,
+//!                        wait_hint: Duration::from_secs(5),
+//!                    });
+//!                    break;
+//!                }
 //!                _ => (),
 //!            }
 //!        }
 //!    }
-//!    0
+//!    ServiceResult::Success
 //! }
 //!
 //! Service!("Foobar", my_service_main);
@@ -0,0 +1,29 @@
+use std::fmt;
+
+/// Opaque identifier for a login session, generic over the platform-specific
+/// id type delivered with the `Session*` [`crate::ServiceEvent`] variants
+/// (a login session identifier string on Linux, a WTS session id on
+/// Windows).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session_<T> {
+    pub identifier: T,
+}
+
+impl<T> Session_<T> {
+    pub fn new(identifier: T) -> Session_<T> {
+        Session_ { identifier }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Session_<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.identifier)
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        mod windows;
+        pub use self::windows::{Session, SessionInfo, SessionState};
+    }
+}
@@ -0,0 +1,237 @@
+use std::ffi::OsString;
+use std::mem;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::ptr;
+
+use winapi::shared::minwindef::{DWORD, LPVOID};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{PROCESS_INFORMATION, STARTUPINFOW};
+use winapi::um::securitybaseapi::DuplicateTokenEx;
+use winapi::um::userenv::{CreateEnvironmentBlock, DestroyEnvironmentBlock};
+use winapi::um::winbase::{CreateProcessAsUserW, CREATE_UNICODE_ENVIRONMENT};
+use winapi::um::winnt::{SecurityImpersonation, TokenPrimary, HANDLE, MAXIMUM_ALLOWED};
+use winapi::um::wtsapi32::{
+    WTSActive, WTSConnectQuery, WTSConnected, WTSDisconnected, WTSDown, WTSEnumerateSessionsW,
+    WTSFreeMemory, WTSIdle, WTSListen, WTSQuerySessionInformationW, WTSQueryUserToken, WTSReset,
+    WTSShadow, WTSUserName, WTS_CURRENT_SERVER_HANDLE, WTS_SESSION_INFOW,
+};
+
+use crate::Error;
+
+use super::Session_;
+
+pub type Session = Session_<DWORD>;
+
+/// The connection state of a WTS session, as returned by
+/// [`Session::enumerate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Active,
+    Connected,
+    ConnectQuery,
+    Shadow,
+    Disconnected,
+    Idle,
+    Listen,
+    Reset,
+    Down,
+    Other,
+}
+
+impl From<DWORD> for SessionState {
+    fn from(value: DWORD) -> Self {
+        match value {
+            WTSActive => SessionState::Active,
+            WTSConnected => SessionState::Connected,
+            WTSConnectQuery => SessionState::ConnectQuery,
+            WTSShadow => SessionState::Shadow,
+            WTSDisconnected => SessionState::Disconnected,
+            WTSIdle => SessionState::Idle,
+            WTSListen => SessionState::Listen,
+            WTSReset => SessionState::Reset,
+            WTSDown => SessionState::Down,
+            _ => SessionState::Other,
+        }
+    }
+}
+
+/// A WTS session as returned by [`Session::enumerate`], carrying its state
+/// and owning user alongside the session itself.
+#[derive(Debug)]
+pub struct SessionInfo {
+    pub session: Session,
+    pub state: SessionState,
+    pub user: String,
+}
+
+fn to_wstring(s: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(s)
+        .encode_wide()
+        .chain(Some(0))
+        .collect()
+}
+
+fn query_user_name(session_id: DWORD) -> String {
+    let mut buffer: *mut u16 = ptr::null_mut();
+    let mut bytes_returned: DWORD = 0;
+
+    let ok = unsafe {
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            WTSUserName,
+            &mut buffer,
+            &mut bytes_returned,
+        )
+    };
+
+    if ok == 0 || buffer.is_null() {
+        return String::new();
+    }
+
+    let len = (bytes_returned as usize / mem::size_of::<u16>()).saturating_sub(1);
+    let name = unsafe {
+        OsString::from_wide(std::slice::from_raw_parts(buffer, len))
+            .to_string_lossy()
+            .to_string()
+    };
+
+    unsafe {
+        WTSFreeMemory(buffer as *mut _);
+    }
+
+    name
+}
+
+impl Session {
+    /// Lists every session known to the terminal services subsystem
+    /// (active, disconnected, or otherwise), with its state and owning
+    /// user, so a service can find the session to launch a UI helper in.
+    pub fn enumerate() -> Result<Vec<SessionInfo>, Error> {
+        let mut sessions_ptr: *mut WTS_SESSION_INFOW = ptr::null_mut();
+        let mut count: DWORD = 0;
+
+        let ok = unsafe {
+            WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut sessions_ptr, &mut count)
+        };
+
+        if ok == 0 {
+            return Err(Error::new(&format!(
+                "WTSEnumerateSessions failed (GetLastError = {})",
+                unsafe { GetLastError() }
+            )));
+        }
+
+        let sessions = unsafe { std::slice::from_raw_parts(sessions_ptr, count as usize) };
+        let result = sessions
+            .iter()
+            .map(|info| SessionInfo {
+                session: Session_::new(info.SessionId),
+                state: SessionState::from(info.State as DWORD),
+                user: query_user_name(info.SessionId),
+            })
+            .collect();
+
+        unsafe {
+            WTSFreeMemory(sessions_ptr as *mut _);
+        }
+
+        Ok(result)
+    }
+
+    /// Duplicates this session's logged-on user token and spawns `command`
+    /// in that session's desktop, so a service reacting to
+    /// `SessionConnect`/`SessionLogon` can start a per-user agent.
+    pub fn run_as(&self, command: &str) -> Result<(), Error> {
+        let mut user_token: HANDLE = ptr::null_mut();
+        if unsafe { WTSQueryUserToken(self.identifier, &mut user_token) } == 0 {
+            return Err(Error::new(&format!(
+                "WTSQueryUserToken failed (GetLastError = {})",
+                unsafe { GetLastError() }
+            )));
+        }
+
+        let mut primary_token: HANDLE = ptr::null_mut();
+        let duplicated = unsafe {
+            DuplicateTokenEx(
+                user_token,
+                MAXIMUM_ALLOWED,
+                ptr::null_mut(),
+                SecurityImpersonation,
+                TokenPrimary,
+                &mut primary_token,
+            )
+        };
+        unsafe {
+            CloseHandle(user_token);
+        }
+
+        if duplicated == 0 {
+            return Err(Error::new(&format!(
+                "DuplicateTokenEx failed (GetLastError = {})",
+                unsafe { GetLastError() }
+            )));
+        }
+
+        // Build the target user's own environment block instead of
+        // inheriting the (non-interactive, session 0) service's, so the
+        // spawned process sees the same environment a normal logon would
+        // give it.
+        let mut environment: LPVOID = ptr::null_mut();
+        if unsafe { CreateEnvironmentBlock(&mut environment, primary_token, 0) } == 0 {
+            unsafe {
+                CloseHandle(primary_token);
+            }
+            return Err(Error::new(&format!(
+                "CreateEnvironmentBlock failed (GetLastError = {})",
+                unsafe { GetLastError() }
+            )));
+        }
+
+        let mut command_line = to_wstring(command);
+        // "winsta0\default" is the interactive window station and desktop;
+        // without it the child inherits the service's own (non-interactive)
+        // desktop and never becomes visible to the user.
+        let mut desktop = to_wstring("winsta0\\default");
+        let mut startup_info: STARTUPINFOW = unsafe { mem::zeroed() };
+        startup_info.cb = mem::size_of::<STARTUPINFOW>() as DWORD;
+        startup_info.lpDesktop = desktop.as_mut_ptr();
+        let mut process_info: PROCESS_INFORMATION = unsafe { mem::zeroed() };
+
+        let spawned = unsafe {
+            CreateProcessAsUserW(
+                primary_token,
+                ptr::null(),
+                command_line.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                CREATE_UNICODE_ENVIRONMENT,
+                environment,
+                ptr::null(),
+                &mut startup_info,
+                &mut process_info,
+            )
+        };
+
+        unsafe {
+            DestroyEnvironmentBlock(environment);
+            CloseHandle(primary_token);
+        }
+
+        if spawned == 0 {
+            return Err(Error::new(&format!(
+                "CreateProcessAsUser failed (GetLastError = {})",
+                unsafe { GetLastError() }
+            )));
+        }
+
+        unsafe {
+            CloseHandle(process_info.hProcess);
+            CloseHandle(process_info.hThread);
+        }
+
+        Ok(())
+    }
+}